@@ -0,0 +1,224 @@
+use anyhow::{anyhow, Result};
+use std::{fs::File, path::PathBuf, str::FromStr};
+use structopt::StructOpt;
+use zip::ZipArchive;
+
+#[cfg(test)]
+use zip::{write::FileOptions, ZipWriter};
+
+#[derive(StructOpt)]
+pub struct InspectArgs {
+    /// Path to the package.zip to inspect
+    package: PathBuf,
+
+    /// Emit the listing as machine-readable JSON instead of a human-readable
+    /// report
+    #[structopt(long)]
+    format: Option<OutputFormat>,
+}
+
+enum OutputFormat {
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            other => Err(anyhow!("unknown output format: {}", other)),
+        }
+    }
+}
+
+struct EntryInfo {
+    name: String,
+    size: u64,
+    compression: String,
+}
+
+/// Opens `args.package`, lists its entries, parses and verifies the
+/// embedded `manifest.json`, and reports the result in the requested
+/// format.
+pub fn inspect(args: InspectArgs) -> Result<()> {
+    let file = File::open(&args.package)?;
+    let mut archive = ZipArchive::new(file)?;
+
+    let mut entries = Vec::with_capacity(archive.len());
+    let mut manifest_json = None;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.name() == "manifest.json" {
+            manifest_json = Some(serde_json::from_reader(&mut entry)?);
+        }
+        entries.push(EntryInfo {
+            name: entry.name().to_owned(),
+            size: entry.size(),
+            compression: format!("{:?}", entry.compression()),
+        });
+    }
+
+    let manifest_json: serde_json::Value =
+        manifest_json.ok_or_else(|| anyhow!("package is missing manifest.json"))?;
+    let warnings = verify(&entries, &manifest_json);
+
+    match args.format {
+        Some(OutputFormat::Json) => {
+            let report = serde_json::json!({
+                "entries": entries.iter().map(|entry| serde_json::json!({
+                    "name": entry.name,
+                    "size": entry.size,
+                    "compression": entry.compression,
+                })).collect::<Vec<_>>(),
+                "manifest": manifest_json,
+                "warnings": warnings,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        None => {
+            println!("entries:");
+            for entry in &entries {
+                println!("  {} ({} bytes, {})", entry.name, entry.size, entry.compression);
+            }
+            println!("manifest.json:");
+            println!("{}", serde_json::to_string_pretty(&manifest_json)?);
+            if warnings.is_empty() {
+                println!("no issues found");
+            } else {
+                println!("warnings:");
+                for warning in &warnings {
+                    println!("  - {}", warning);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every entry-point's `wasm-path` exists in the archive and
+/// that mount-points declare either `rw` or `ro`. Returns a list of
+/// human-readable warnings; an empty list means the package is valid.
+fn verify(entries: &[EntryInfo], manifest: &serde_json::Value) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let entry_points = manifest
+        .get("entry-points")
+        .and_then(|value| value.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for entry_point in &entry_points {
+        match entry_point.get("wasm-path").and_then(|value| value.as_str()) {
+            Some(wasm_path) => {
+                if !entries.iter().any(|entry| entry.name == wasm_path) {
+                    warnings.push(format!(
+                        "entry-point references '{}' which is not present in the archive",
+                        wasm_path
+                    ));
+                }
+            }
+            None => warnings.push("entry-point is missing a 'wasm-path'".to_owned()),
+        }
+    }
+
+    let mount_points = manifest
+        .get("mount-points")
+        .and_then(|value| value.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for mount_point in &mount_points {
+        let has_rw = mount_point.get("rw").and_then(|value| value.as_str()).is_some();
+        let has_ro = mount_point.get("ro").and_then(|value| value.as_str()).is_some();
+        if !has_rw && !has_ro {
+            warnings.push("mount-point declares neither 'rw' nor 'ro'".to_owned());
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn entry(name: &str) -> EntryInfo {
+        EntryInfo {
+            name: name.to_owned(),
+            size: 0,
+            compression: "Stored".to_owned(),
+        }
+    }
+
+    #[test]
+    fn verify_accepts_a_well_formed_manifest() {
+        let entries = vec![entry("main.wasm"), entry("manifest.json")];
+        let manifest = serde_json::json!({
+            "entry-points": [{"id": "main", "wasm-path": "main.wasm"}],
+            "mount-points": [{"rw": "workdir"}],
+        });
+
+        assert!(verify(&entries, &manifest).is_empty());
+    }
+
+    #[test]
+    fn verify_flags_a_missing_wasm_path() {
+        let entries = vec![entry("manifest.json")];
+        let manifest = serde_json::json!({
+            "entry-points": [{"id": "main", "wasm-path": "main.wasm"}],
+            "mount-points": [],
+        });
+
+        let warnings = verify(&entries, &manifest);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("main.wasm"));
+    }
+
+    #[test]
+    fn verify_flags_a_mount_point_with_neither_rw_nor_ro() {
+        let entries = vec![entry("manifest.json")];
+        let manifest = serde_json::json!({
+            "entry-points": [],
+            "mount-points": [{}],
+        });
+
+        let warnings = verify(&entries, &manifest);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("mount-point"));
+    }
+
+    #[test]
+    fn inspect_reads_manifest_json_out_of_a_real_archive() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("wasi-requestor-inspect-test-{}.zip", std::process::id()));
+
+        let file = File::create(&path).unwrap();
+        let mut zip_writer = ZipWriter::new(file);
+        let options = FileOptions::default();
+        zip_writer.start_file("main.wasm", options).unwrap();
+        zip_writer.write_all(b"\0asm").unwrap();
+        zip_writer.start_file("manifest.json", options).unwrap();
+        zip_writer
+            .write_all(
+                serde_json::json!({
+                    "id": "custom",
+                    "name": "custom",
+                    "entry-points": [{"id": "main", "wasm-path": "main.wasm"}],
+                    "mount-points": [{"rw": "workdir"}],
+                })
+                .to_string()
+                .as_bytes(),
+            )
+            .unwrap();
+        zip_writer.finish().unwrap();
+
+        let result = inspect(InspectArgs {
+            package: path.clone(),
+            format: None,
+        });
+        std::fs::remove_file(&path).ok();
+
+        assert!(result.is_ok());
+    }
+}
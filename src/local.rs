@@ -0,0 +1,166 @@
+use anyhow::{anyhow, Context, Result};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+use wasmtime::{Linker, Module, Store};
+use wasmtime_wasi::{Wasi, WasiCtxBuilder};
+
+use crate::{manifest, pipeline};
+
+#[derive(StructOpt)]
+pub struct RunLocalArgs {
+    /// Wasm module. Ignored when `--manifest` is given, as the manifest's
+    /// entry-points declare the modules to run instead.
+    module: Option<PathBuf>,
+
+    /// Args
+    args: Vec<String>,
+
+    /// Path to a YAML or JSON task-pipeline spec, as accepted by `run
+    /// --tasks`.
+    #[structopt(long, parse(from_os_str))]
+    tasks: Option<PathBuf>,
+
+    /// Path to a TOML manifest config, as accepted by `run --manifest`.
+    #[structopt(long, parse(from_os_str))]
+    manifest: Option<PathBuf>,
+
+    /// Host directory preopened into the guest as `/workdir`
+    #[structopt(long, parse(from_os_str), default_value = ".")]
+    workdir: PathBuf,
+}
+
+/// Runs the declared tasks against an embedded `wasmtime` engine instead of
+/// submitting them to the network, so a module and its pipeline can be
+/// validated for free before paying to submit them.
+pub fn run_local(args: RunLocalArgs) -> Result<()> {
+    let modules = resolve_modules(&args)?;
+    let tasks = resolve_tasks(&args)?;
+    fs::create_dir_all(&args.workdir)?;
+
+    for (i, task) in tasks.into_iter().enumerate() {
+        log::info!("running task {}", i);
+        run_task(&args.workdir, &modules, task)?;
+    }
+
+    Ok(())
+}
+
+fn resolve_modules(args: &RunLocalArgs) -> Result<HashMap<String, PathBuf>> {
+    match &args.manifest {
+        Some(manifest_path) => {
+            let config = manifest::load(manifest_path)?;
+            Ok(config
+                .entry_points
+                .into_iter()
+                .map(|entry_point| (entry_point.id, PathBuf::from(entry_point.wasm_path)))
+                .collect())
+        }
+        None => {
+            let module = args
+                .module
+                .clone()
+                .ok_or_else(|| anyhow!("a module path is required unless --manifest is given"))?;
+            let mut modules = HashMap::new();
+            modules.insert("custom".to_owned(), module);
+            Ok(modules)
+        }
+    }
+}
+
+fn resolve_tasks(args: &RunLocalArgs) -> Result<Vec<pipeline::Task>> {
+    match &args.tasks {
+        Some(path) => pipeline::load_tasks(path),
+        None => {
+            let input = args
+                .args
+                .get(0)
+                .ok_or_else(|| anyhow!("expected an input file argument"))?;
+            let output = args
+                .args
+                .get(1)
+                .ok_or_else(|| anyhow!("expected an output file argument"))?;
+            Ok(vec![pipeline::default_task(input, output)])
+        }
+    }
+}
+
+fn run_task(workdir: &Path, modules: &HashMap<String, PathBuf>, task: pipeline::Task) -> Result<()> {
+    for step in task {
+        match step {
+            pipeline::Step::Upload { host, guest } => {
+                let dest = workdir.join(workdir_relative_path(&guest)?);
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(&host, &dest).with_context(|| format!("uploading '{}'", host))?;
+            }
+            pipeline::Step::Run { entrypoint, args } => {
+                let wasm_path = modules
+                    .get(&entrypoint)
+                    .ok_or_else(|| anyhow!("unknown entry-point '{}'", entrypoint))?;
+                execute(wasm_path, workdir, &args)
+                    .with_context(|| format!("running entry-point '{}'", entrypoint))?;
+            }
+            pipeline::Step::Download { guest, host } => {
+                let src = workdir.join(workdir_relative_path(&guest)?);
+                fs::copy(&src, &host).with_context(|| format!("downloading '{}'", host))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves a step's guest path to a path relative to `workdir`, rejecting
+/// anything that doesn't live under `/workdir` — that's the only directory
+/// actually preopened into the guest, so any other path would silently
+/// read or write outside the sandbox on the host side.
+fn workdir_relative_path(guest_path: &str) -> Result<&str> {
+    guest_path
+        .strip_prefix("/workdir/")
+        .ok_or_else(|| anyhow!("guest path '{}' must live under /workdir/", guest_path))
+}
+
+fn execute(wasm_path: &Path, workdir: &Path, args: &[String]) -> Result<()> {
+    let store = Store::default();
+    let mut linker = Linker::new(&store);
+
+    let preopen_dir = wasmtime_wasi::Dir::open_ambient_dir(workdir, wasmtime_wasi::ambient_authority())?;
+    let wasi_ctx = WasiCtxBuilder::new()
+        .inherit_stdio()
+        .args(args)
+        .preopened_dir(preopen_dir, "/workdir")?
+        .build()?;
+    Wasi::new(&store, wasi_ctx).add_to_linker(&mut linker)?;
+
+    let module = Module::from_file(store.engine(), wasm_path)?;
+    let instance = linker.instantiate(&module)?;
+    let start = instance
+        .get_func("_start")
+        .ok_or_else(|| anyhow!("module '{}' has no '_start' export", wasm_path.display()))?;
+    start.call(&[])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workdir_relative_path_accepts_paths_under_workdir() {
+        assert_eq!(workdir_relative_path("/workdir/out.txt").unwrap(), "out.txt");
+        assert_eq!(workdir_relative_path("/workdir/nested/out.txt").unwrap(), "nested/out.txt");
+    }
+
+    #[test]
+    fn workdir_relative_path_rejects_paths_outside_workdir() {
+        assert!(workdir_relative_path("/etc/passwd").is_err());
+        assert!(workdir_relative_path("/workdirevil/out.txt").is_err());
+        assert!(workdir_relative_path("workdir/out.txt").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fs, path::Path};
+
+/// A single packaged module's recorded digest, as stored in `package.lock`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleEntry {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Recorded inputs of a package, so the same bytes can be reproduced
+/// across machines and CI runs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub modules: Vec<ModuleEntry>,
+    pub manifest_sha256: String,
+    pub compression: String,
+    pub compression_level: Option<i32>,
+}
+
+/// Hashes `bytes` with SHA-256, returning the lowercase hex digest.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Loads a lockfile from `path`, returning `None` if it doesn't exist yet.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Lockfile>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(path)?;
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+/// Writes `lockfile` to `path` as pretty-printed JSON.
+pub fn write<P: AsRef<Path>>(path: P, lockfile: &Lockfile) -> Result<()> {
+    fs::write(path, serde_json::to_vec_pretty(lockfile)?)?;
+    Ok(())
+}
+
+/// Compares a freshly computed `Lockfile` against a previously recorded
+/// one, returning a human-readable mismatch per difference found. An empty
+/// result means the inputs reproduce the recorded package exactly.
+pub fn verify(recorded: &Lockfile, current: &Lockfile) -> Vec<String> {
+    let mut mismatches = Vec::new();
+
+    if recorded.modules.len() != current.modules.len() {
+        mismatches.push(format!(
+            "module count changed: {} -> {}",
+            recorded.modules.len(),
+            current.modules.len()
+        ));
+    }
+
+    for (recorded_module, current_module) in recorded.modules.iter().zip(current.modules.iter()) {
+        if recorded_module.name != current_module.name || recorded_module.sha256 != current_module.sha256 {
+            mismatches.push(format!(
+                "module '{}' changed (recorded sha256 {}, now '{}' with sha256 {})",
+                recorded_module.name, recorded_module.sha256, current_module.name, current_module.sha256
+            ));
+        }
+    }
+
+    if recorded.manifest_sha256 != current.manifest_sha256 {
+        mismatches.push("manifest.json changed".to_owned());
+    }
+
+    if recorded.compression != current.compression || recorded.compression_level != current.compression_level {
+        mismatches.push("compression settings changed".to_owned());
+    }
+
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lockfile() -> Lockfile {
+        Lockfile {
+            modules: vec![ModuleEntry {
+                name: "main.wasm".to_owned(),
+                size: 1024,
+                sha256: "abc123".to_owned(),
+            }],
+            manifest_sha256: "def456".to_owned(),
+            compression: "Deflated".to_owned(),
+            compression_level: Some(6),
+        }
+    }
+
+    #[test]
+    fn hash_bytes_is_deterministic() {
+        assert_eq!(hash_bytes(b"hello"), hash_bytes(b"hello"));
+    }
+
+    #[test]
+    fn hash_bytes_distinguishes_inputs() {
+        assert_ne!(hash_bytes(b"hello"), hash_bytes(b"world"));
+    }
+
+    #[test]
+    fn verify_accepts_identical_lockfiles() {
+        assert!(verify(&lockfile(), &lockfile()).is_empty());
+    }
+
+    #[test]
+    fn verify_flags_module_count_change() {
+        let recorded = lockfile();
+        let mut current = lockfile();
+        current.modules.push(ModuleEntry {
+            name: "extra.wasm".to_owned(),
+            size: 10,
+            sha256: "xyz".to_owned(),
+        });
+
+        let mismatches = verify(&recorded, &current);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("module count changed"));
+    }
+
+    #[test]
+    fn verify_flags_module_hash_change() {
+        let recorded = lockfile();
+        let mut current = lockfile();
+        current.modules[0].sha256 = "different".to_owned();
+
+        let mismatches = verify(&recorded, &current);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("main.wasm"));
+    }
+
+    #[test]
+    fn verify_flags_manifest_change() {
+        let recorded = lockfile();
+        let mut current = lockfile();
+        current.manifest_sha256 = "changed".to_owned();
+
+        let mismatches = verify(&recorded, &current);
+        assert_eq!(mismatches, vec!["manifest.json changed".to_owned()]);
+    }
+
+    #[test]
+    fn verify_flags_compression_change() {
+        let recorded = lockfile();
+        let mut current = lockfile();
+        current.compression_level = Some(9);
+
+        let mismatches = verify(&recorded, &current);
+        assert_eq!(mismatches, vec!["compression settings changed".to_owned()]);
+    }
+}
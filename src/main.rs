@@ -1,40 +1,154 @@
 use anyhow::{anyhow, Result};
-use futures::{future::FutureExt, pin_mut, select};
+use futures::{future::FutureExt, pin_mut, select, stream::StreamExt};
 use std::{
+    collections::HashMap,
     fs,
     io::{Cursor, Write},
     path::{Path, PathBuf},
-    collections::HashMap,
+    str::FromStr,
 };
 use structopt::StructOpt;
 use ya_agreement_utils::{constraints, ConstraintKey, Constraints};
-use ya_requestor_sdk::{commands, CommandList, Image::WebAssembly, Requestor};
+use ya_requestor_sdk::{CommandList, Image::WebAssembly, Requestor};
 use zip::{write::FileOptions, CompressionMethod, ZipWriter};
 
+mod inspect;
+mod local;
+mod lockfile;
+mod manifest;
+mod pipeline;
+mod progress;
+
+use inspect::InspectArgs;
+use local::RunLocalArgs;
+
 #[derive(StructOpt)]
-struct Args {
-    /// Wasm module
-    module: PathBuf,
+enum Args {
+    /// Package and submit a module (or task-pipeline spec) to the network
+    Run(RunArgs),
+    /// Run a module (or task-pipeline spec) locally under wasmtime, without
+    /// submitting anything to the network
+    RunLocal(RunLocalArgs),
+    /// List and verify the contents of an existing package.zip
+    Inspect(InspectArgs),
+}
+
+#[derive(StructOpt)]
+struct RunArgs {
+    /// Wasm module. Ignored when `--manifest` is given, as the manifest's
+    /// entry-points declare the modules to package instead.
+    module: Option<PathBuf>,
 
     /// Args
     args: Vec<String>,
+
+    /// Path to a YAML or JSON task-pipeline spec. Each task is an ordered
+    /// list of upload/run/download steps; when given, this replaces the
+    /// single upload/run/download pipeline built from `args`.
+    #[structopt(long, parse(from_os_str))]
+    tasks: Option<PathBuf>,
+
+    /// Path to a TOML manifest config declaring the package `id`/`name`,
+    /// one or more entry-points and one or more mount-points. Enables
+    /// packaging multiple Wasm modules in a single package.
+    #[structopt(long, parse(from_os_str))]
+    manifest: Option<PathBuf>,
+
+    /// Compression method used when packaging the module
+    #[structopt(long, default_value = "stored")]
+    compression: Compression,
+
+    /// Compression level passed through to the zip writer, if the chosen
+    /// compression method supports one
+    #[structopt(long)]
+    compression_level: Option<i32>,
+
+    /// Path to a lockfile recording per-module hashes, sizes, and the
+    /// chosen compression settings. Written on first use; on subsequent
+    /// runs, packaging is verified against it so the same bytes are
+    /// reproduced across machines and CI runs.
+    #[structopt(long, parse(from_os_str))]
+    lockfile: Option<PathBuf>,
+
+    /// Hard-fail instead of warning when `--lockfile` verification finds a
+    /// mismatch
+    #[structopt(long)]
+    locked: bool,
+}
+
+/// Compression method for the package zip, selectable via `--compression`.
+#[derive(Clone, Copy, Debug)]
+enum Compression {
+    Stored,
+    Deflate,
+    Bzip2,
+    Zstd,
+}
+
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "stored" => Ok(Compression::Stored),
+            "deflate" => Ok(Compression::Deflate),
+            "bzip2" => Ok(Compression::Bzip2),
+            "zstd" => Ok(Compression::Zstd),
+            other => Err(anyhow!("unknown compression method: {}", other)),
+        }
+    }
+}
+
+impl From<Compression> for CompressionMethod {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::Stored => CompressionMethod::Stored,
+            Compression::Deflate => CompressionMethod::Deflated,
+            Compression::Bzip2 => CompressionMethod::Bzip2,
+            Compression::Zstd => CompressionMethod::Zstd,
+        }
+    }
+}
+
+/// Unix mode applied to every entry written into the package, so the
+/// resulting archive is reproducible across platforms.
+const ENTRY_UNIX_MODE: u32 = 0o644;
+
+/// Digests and byte counts of the modules and manifest that went into a
+/// package, used to produce and verify its `--lockfile`.
+struct PackageSummary {
+    modules: Vec<lockfile::ModuleEntry>,
+    manifest_sha256: String,
 }
 
 struct Package {
     zip_writer: ZipWriter<Cursor<Vec<u8>>>,
     options: FileOptions,
-    module_name: Option<String>,
+    module_names: Vec<String>,
+    module_entries: Vec<lockfile::ModuleEntry>,
+    progress: Option<progress::Sender>,
 }
 
 impl Package {
-    fn new() -> Self {
-        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+    fn new(compression: Compression, compression_level: Option<i32>, progress: Option<progress::Sender>) -> Self {
+        let options = FileOptions::default()
+            .compression_method(compression.into())
+            .compression_level(compression_level)
+            .unix_permissions(ENTRY_UNIX_MODE);
         let zip_writer = ZipWriter::new(Cursor::new(Vec::new()));
 
         Self {
             zip_writer,
             options,
-            module_name: None,
+            module_names: Vec::new(),
+            module_entries: Vec::new(),
+            progress,
+        }
+    }
+
+    fn emit(&self, event: progress::Event) {
+        if let Some(sender) = &self.progress {
+            let _ = sender.unbounded_send(event);
         }
     }
 
@@ -50,51 +164,139 @@ impl Package {
         self.zip_writer
             .start_file(&module_name, self.options.clone())?;
         self.zip_writer.write(&contents)?;
-        self.module_name = Some(module_name);
+        self.emit(progress::Event::ModuleAdded {
+            name: module_name.clone(),
+            bytes: contents.len() as u64,
+        });
+        self.module_entries.push(lockfile::ModuleEntry {
+            name: module_name.clone(),
+            size: contents.len() as u64,
+            sha256: lockfile::hash_bytes(&contents),
+        });
+        self.module_names.push(module_name);
 
         Ok(())
     }
 
-    fn write<P: AsRef<Path>>(mut self, path: P) -> Result<()> {
-        // create manifest
-        let comps: Vec<_> = self.module_name.as_ref().unwrap().split('.').collect();
-        let manifest = serde_json::json!({
-            "id": "custom",
-            "name": "custom",
-            "entry-points": [{
-                "id": comps[0],
-                "wasm-path": self.module_name.unwrap(),
-            }],
-            "mount-points": [{
-                "rw": "workdir",
-            }]
-        });
+    fn write<P: AsRef<Path>>(
+        mut self,
+        path: P,
+        manifest_config: Option<manifest::ManifestConfig>,
+    ) -> Result<PackageSummary> {
+        let manifest = match manifest_config {
+            Some(config) => config.to_json(),
+            None => {
+                let module_name = self.module_names.first().unwrap();
+                let comps: Vec<_> = module_name.split('.').collect();
+                serde_json::json!({
+                    "id": "custom",
+                    "name": "custom",
+                    "entry-points": [{
+                        "id": comps[0],
+                        "wasm-path": module_name,
+                    }],
+                    "mount-points": [{
+                        "rw": "workdir",
+                    }]
+                })
+            }
+        };
+        let manifest_bytes = serde_json::to_vec(&manifest)?;
+        let manifest_sha256 = lockfile::hash_bytes(&manifest_bytes);
         self.zip_writer
             .start_file("manifest.json", self.options.clone())?;
-        self.zip_writer.write(&serde_json::to_vec(&manifest)?)?;
+        self.zip_writer.write(&manifest_bytes)?;
+        self.emit(progress::Event::ManifestWritten);
 
         let finalized = self.zip_writer.finish()?.into_inner();
+        self.emit(progress::Event::PackageFinalized {
+            total_bytes: finalized.len() as u64,
+        });
         fs::write(path.as_ref(), finalized)?;
 
-        Ok(())
+        Ok(PackageSummary {
+            modules: self.module_entries,
+            manifest_sha256,
+        })
     }
 }
 
 #[actix_rt::main]
 async fn main() -> Result<()> {
     let _ = dotenv::dotenv().ok();
-    let args = Args::from_args();
     pretty_env_logger::init();
 
+    match Args::from_args() {
+        Args::Run(args) => run(args).await,
+        Args::RunLocal(args) => local::run_local(args),
+        Args::Inspect(args) => inspect::inspect(args),
+    }
+}
+
+async fn run(args: RunArgs) -> Result<()> {
+    let (progress_tx, progress_rx) = progress::channel();
+    actix_rt::spawn(render_progress(progress_rx));
+
     // Workspace
     let workspace = tempfile::tempdir()?;
     log::info!("Workspace created in '{}'", workspace.path().display());
 
     // Prepare the zip package
     let package_path = workspace.path().join("package.zip");
-    let mut package = Package::new();
-    package.add_module_from_path(&args.module)?;
-    package.write(&package_path)?;
+    let manifest_config = args.manifest.as_ref().map(manifest::load).transpose()?;
+
+    let mut package = Package::new(args.compression, args.compression_level, Some(progress_tx.clone()));
+    match &manifest_config {
+        Some(config) => {
+            for entry_point in &config.entry_points {
+                package.add_module_from_path(&entry_point.wasm_path)?;
+            }
+        }
+        None => {
+            let module = args
+                .module
+                .as_ref()
+                .ok_or_else(|| anyhow!("a module path is required unless --manifest is given"))?;
+            package.add_module_from_path(module)?;
+        }
+    }
+    let summary = package.write(&package_path, manifest_config)?;
+    if let Some(lockfile_path) = &args.lockfile {
+        verify_or_write_lockfile(lockfile_path, summary, args.compression, args.compression_level, args.locked)?;
+    }
+
+    let tasks: Vec<pipeline::Task> = match &args.tasks {
+        Some(path) => pipeline::load_tasks(path)?,
+        None => {
+            if args.args.len() < 2 {
+                return Err(anyhow!(
+                    "expected an input and output file argument, or --tasks"
+                ));
+            }
+            vec![pipeline::default_task(&args.args[0], &args.args[1])]
+        }
+    };
+    let command_lists: Vec<CommandList> = pipeline::into_command_lists(tasks.clone());
+
+    // Every step of every task, in submission order. The SDK doesn't expose
+    // hooks into its internal execution, so `Started`/`Completed` can only
+    // be reported at the two points we actually observe: right before
+    // submission and when the whole batch resolves — but each event is at
+    // least tagged with its real step kind and task, unlike a single
+    // hardcoded `Run` marker per task.
+    let step_plan: Vec<(usize, progress::StepKind)> = tasks
+        .iter()
+        .enumerate()
+        .flat_map(|(task_id, task)| task.iter().map(move |step| (task_id, step_kind(step))))
+        .collect();
+
+    for (task_id, step) in &step_plan {
+        let _ = progress_tx.unbounded_send(progress::Event::TaskProgress {
+            task_id: *task_id,
+            step: *step,
+            state: progress::StepState::Started,
+        });
+    }
 
     let requestor = Requestor::new(
         "kubkon-requestor-agent",
@@ -107,12 +309,15 @@ async fn main() -> Result<()> {
         "golem.inf.storage.gib" > 1.0,
         "golem.com.pricing.model" == "linear",
     ])
-    .with_tasks(vec![commands! {
-        upload(&args.args[0], format!("/workdir/{}", &args.args[0]));
-        run("custom", format!("/workdir/{}", &args.args[0]), format!("/workdir/{}", &args.args[1]));
-        download(format!("/workdir/{}", &args.args[1]), &args.args[1]);
-    }].into_iter())
-    .on_completed(|outputs: HashMap<String, String>| {
+    .with_tasks(command_lists.into_iter())
+    .on_completed(move |outputs: HashMap<String, String>| {
+        for (task_id, step) in &step_plan {
+            let _ = progress_tx.unbounded_send(progress::Event::TaskProgress {
+                task_id: *task_id,
+                step: *step,
+                state: progress::StepState::Completed,
+            });
+        }
         println!("{:#?}", outputs);
     })
     .run().fuse();
@@ -126,3 +331,73 @@ async fn main() -> Result<()> {
         _ = ctrl_c => Err(anyhow!("interrupted: ctrl-c detected!")),
     }
 }
+
+/// Maps a pipeline step to the kind reported in `TaskProgress` events.
+fn step_kind(step: &pipeline::Step) -> progress::StepKind {
+    match step {
+        pipeline::Step::Upload { .. } => progress::StepKind::Upload,
+        pipeline::Step::Run { .. } => progress::StepKind::Run,
+        pipeline::Step::Download { .. } => progress::StepKind::Download,
+    }
+}
+
+/// Renders packaging and task progress events as they arrive, printing
+/// human-readable byte counts and per-task state.
+async fn render_progress(mut events: progress::Receiver) {
+    while let Some(event) = events.next().await {
+        match event {
+            progress::Event::ModuleAdded { name, bytes } => {
+                println!("packaging: added '{}' ({})", name, progress::human_bytes(bytes));
+            }
+            progress::Event::ManifestWritten => {
+                println!("packaging: wrote manifest.json");
+            }
+            progress::Event::PackageFinalized { total_bytes } => {
+                println!("packaging: done ({}, 100%)", progress::human_bytes(total_bytes));
+            }
+            progress::Event::TaskProgress { task_id, step, state } => {
+                println!("task {}: {:?} {:?}", task_id, step, state);
+            }
+        }
+    }
+}
+
+/// Verifies `summary` against a previously recorded lockfile at
+/// `lockfile_path`, if one exists. Mismatches are reported as warnings
+/// unless `locked` is set, in which case they are a hard error. The
+/// lockfile is (re-)written only when it didn't exist yet or matched —
+/// never on an unresolved mismatch, or drift could never be caught twice.
+fn verify_or_write_lockfile(
+    lockfile_path: &Path,
+    summary: PackageSummary,
+    compression: Compression,
+    compression_level: Option<i32>,
+    locked: bool,
+) -> Result<()> {
+    let current = lockfile::Lockfile {
+        modules: summary.modules,
+        manifest_sha256: summary.manifest_sha256,
+        compression: format!("{:?}", compression),
+        compression_level,
+    };
+
+    if let Some(recorded) = lockfile::load(lockfile_path)? {
+        let mismatches = lockfile::verify(&recorded, &current);
+        if !mismatches.is_empty() {
+            if locked {
+                return Err(anyhow!(
+                    "package does not match '{}':\n{}",
+                    lockfile_path.display(),
+                    mismatches.join("\n")
+                ));
+            }
+            log::warn!("package does not match '{}':", lockfile_path.display());
+            for mismatch in &mismatches {
+                log::warn!("  {}", mismatch);
+            }
+            return Ok(());
+        }
+    }
+
+    lockfile::write(lockfile_path, &current)
+}
@@ -0,0 +1,178 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::{collections::HashSet, path::Path};
+
+/// User-declared package manifest, read from a `--manifest <toml>` file and
+/// modeled on how crate metadata is declared: a package `id`/`name`, one or
+/// more `[[entry-point]]` tables, and one or more `[[mount-point]]` tables.
+#[derive(Debug, Deserialize)]
+pub struct ManifestConfig {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "entry-point")]
+    pub entry_points: Vec<EntryPoint>,
+    #[serde(rename = "mount-point")]
+    pub mount_points: Vec<MountPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EntryPoint {
+    pub id: String,
+    #[serde(rename = "wasm-path")]
+    pub wasm_path: String,
+}
+
+impl EntryPoint {
+    /// The name under which `wasm_path` is archived by
+    /// `Package::add_module_from_path`, i.e. its basename. The manifest
+    /// must reference modules by this name, not the host path, since only
+    /// the basename is stored in the zip.
+    pub fn archived_name(&self) -> &str {
+        Path::new(&self.wasm_path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&self.wasm_path)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MountPoint {
+    pub rw: Option<String>,
+    pub ro: Option<String>,
+}
+
+/// Loads a `ManifestConfig` from a TOML file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<ManifestConfig> {
+    let contents = std::fs::read_to_string(path)?;
+    let config: ManifestConfig = toml::from_str(&contents)?;
+    check_archived_names_are_unique(&config)?;
+    Ok(config)
+}
+
+/// Ensures no two entry-points archive to the same basename, since
+/// `Package::add_module_from_path` stores modules by basename alone — two
+/// `wasm-path`s colliding on their basename would silently overwrite one
+/// archive entry with the other, leaving one entry-point pointing at the
+/// wrong module.
+fn check_archived_names_are_unique(config: &ManifestConfig) -> Result<()> {
+    let mut seen = HashSet::new();
+    for entry_point in &config.entry_points {
+        if !seen.insert(entry_point.archived_name()) {
+            return Err(anyhow!(
+                "entry-point '{}' archives to '{}', which collides with another entry-point's wasm-path basename",
+                entry_point.id,
+                entry_point.archived_name()
+            ));
+        }
+    }
+    Ok(())
+}
+
+impl ManifestConfig {
+    /// Serializes the config into the `manifest.json` shape expected inside
+    /// a package zip.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "name": self.name,
+            "entry-points": self.entry_points.iter().map(|entry_point| serde_json::json!({
+                "id": entry_point.id,
+                "wasm-path": entry_point.archived_name(),
+            })).collect::<Vec<_>>(),
+            "mount-points": self.mount_points.iter().map(MountPoint::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl MountPoint {
+    fn to_json(&self) -> serde_json::Value {
+        let mut obj = serde_json::Map::new();
+        if let Some(rw) = &self.rw {
+            obj.insert("rw".to_owned(), serde_json::json!(rw));
+        }
+        if let Some(ro) = &self.ro {
+            obj.insert("ro".to_owned(), serde_json::json!(ro));
+        }
+        serde_json::Value::Object(obj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archived_name_strips_the_host_directory() {
+        let entry_point = EntryPoint {
+            id: "main".to_owned(),
+            wasm_path: "target/wasm32-wasi/release/foo.wasm".to_owned(),
+        };
+
+        assert_eq!(entry_point.archived_name(), "foo.wasm");
+    }
+
+    #[test]
+    fn to_json_references_archived_basenames_not_host_paths() {
+        let config = ManifestConfig {
+            id: "custom".to_owned(),
+            name: "custom".to_owned(),
+            entry_points: vec![EntryPoint {
+                id: "main".to_owned(),
+                wasm_path: "build/out/main.wasm".to_owned(),
+            }],
+            mount_points: vec![MountPoint {
+                rw: Some("workdir".to_owned()),
+                ro: None,
+            }],
+        };
+
+        let json = config.to_json();
+
+        assert_eq!(json["entry-points"][0]["wasm-path"], "main.wasm");
+        assert_eq!(json["entry-points"][0]["id"], "main");
+        assert_eq!(json["mount-points"][0]["rw"], "workdir");
+        assert!(json["mount-points"][0].get("ro").is_none());
+    }
+
+    #[test]
+    fn check_archived_names_are_unique_rejects_colliding_basenames() {
+        let config = ManifestConfig {
+            id: "custom".to_owned(),
+            name: "custom".to_owned(),
+            entry_points: vec![
+                EntryPoint {
+                    id: "a".to_owned(),
+                    wasm_path: "build-a/out.wasm".to_owned(),
+                },
+                EntryPoint {
+                    id: "b".to_owned(),
+                    wasm_path: "build-b/out.wasm".to_owned(),
+                },
+            ],
+            mount_points: vec![],
+        };
+
+        assert!(check_archived_names_are_unique(&config).is_err());
+    }
+
+    #[test]
+    fn check_archived_names_are_unique_accepts_distinct_basenames() {
+        let config = ManifestConfig {
+            id: "custom".to_owned(),
+            name: "custom".to_owned(),
+            entry_points: vec![
+                EntryPoint {
+                    id: "a".to_owned(),
+                    wasm_path: "build-a/a.wasm".to_owned(),
+                },
+                EntryPoint {
+                    id: "b".to_owned(),
+                    wasm_path: "build-b/b.wasm".to_owned(),
+                },
+            ],
+            mount_points: vec![],
+        };
+
+        assert!(check_archived_names_are_unique(&config).is_ok());
+    }
+}
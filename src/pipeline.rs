@@ -0,0 +1,134 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::path::Path;
+use ya_requestor_sdk::{Command, CommandList};
+
+/// A single step in a task's pipeline, as declared in a `--tasks` spec file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    Upload { host: String, guest: String },
+    Run { entrypoint: String, args: Vec<String> },
+    Download { guest: String, host: String },
+}
+
+/// An ordered list of steps executed as a single unit of work.
+pub type Task = Vec<Step>;
+
+/// Loads a list of tasks from a YAML or JSON file, picking the format based
+/// on the file's extension.
+pub fn load_tasks<P: AsRef<Path>>(path: P) -> Result<Vec<Task>> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)?;
+    let tasks = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)?,
+        Some("json") => serde_json::from_str(&contents)?,
+        other => bail!("unsupported task spec extension: {:?}", other),
+    };
+
+    Ok(tasks)
+}
+
+/// Builds the default single upload/run/download task used when no
+/// `--tasks` spec is given.
+pub fn default_task(input: &str, output: &str) -> Task {
+    vec![
+        Step::Upload {
+            host: input.to_owned(),
+            guest: format!("/workdir/{}", input),
+        },
+        Step::Run {
+            entrypoint: "custom".to_owned(),
+            args: vec![format!("/workdir/{}", input), format!("/workdir/{}", output)],
+        },
+        Step::Download {
+            guest: format!("/workdir/{}", output),
+            host: output.to_owned(),
+        },
+    ]
+}
+
+fn step_to_command(step: Step) -> Command {
+    match step {
+        Step::Upload { host, guest } => Command::Upload { from: host, to: guest },
+        Step::Run { entrypoint, args } => Command::Run {
+            entry_point: entrypoint,
+            args,
+        },
+        Step::Download { guest, host } => Command::Download { from: guest, to: host },
+    }
+}
+
+/// Converts the loaded tasks into the `CommandList`s expected by
+/// `Requestor::with_tasks`.
+pub fn into_command_lists(tasks: Vec<Task>) -> Vec<CommandList> {
+    tasks
+        .into_iter()
+        .map(|task| CommandList::new(task.into_iter().map(step_to_command).collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_task_uploads_runs_then_downloads() {
+        let task = default_task("in.txt", "out.txt");
+
+        assert_eq!(task.len(), 3);
+        assert!(matches!(
+            &task[0],
+            Step::Upload { host, guest } if host == "in.txt" && guest == "/workdir/in.txt"
+        ));
+        assert!(matches!(
+            &task[1],
+            Step::Run { entrypoint, args }
+                if entrypoint == "custom" && args == &["/workdir/in.txt".to_owned(), "/workdir/out.txt".to_owned()]
+        ));
+        assert!(matches!(
+            &task[2],
+            Step::Download { guest, host } if guest == "/workdir/out.txt" && host == "out.txt"
+        ));
+    }
+
+    #[test]
+    fn load_tasks_parses_json() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("wasi-requestor-test-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"[[
+                {"upload": {"host": "a.txt", "guest": "/workdir/a.txt"}},
+                {"run": {"entrypoint": "custom", "args": ["/workdir/a.txt"]}},
+                {"download": {"guest": "/workdir/a.txt", "host": "a.txt"}}
+            ]]"#,
+        )
+        .unwrap();
+
+        let tasks = load_tasks(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].len(), 3);
+    }
+
+    #[test]
+    fn load_tasks_rejects_unknown_extension() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("wasi-requestor-test-{}.txt", std::process::id()));
+        std::fs::write(&path, "[]").unwrap();
+
+        let result = load_tasks(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn into_command_lists_preserves_task_count() {
+        let tasks = vec![default_task("in.txt", "out.txt"), default_task("in2.txt", "out2.txt")];
+        let command_lists = into_command_lists(tasks);
+        assert_eq!(command_lists.len(), 2);
+    }
+}
@@ -0,0 +1,56 @@
+use futures::channel::mpsc;
+
+/// A structured progress event emitted during packaging and task execution.
+/// Exposing these over a channel (rather than printing directly) lets the
+/// packaging API be driven from a GUI or background thread, not just the
+/// CLI's own renderer.
+#[derive(Debug, Clone)]
+pub enum Event {
+    ModuleAdded { name: String, bytes: u64 },
+    ManifestWritten,
+    PackageFinalized { total_bytes: u64 },
+    TaskProgress {
+        task_id: usize,
+        step: StepKind,
+        state: StepState,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    Upload,
+    Run,
+    Download,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepState {
+    Started,
+    Completed,
+}
+
+pub type Sender = mpsc::UnboundedSender<Event>;
+pub type Receiver = mpsc::UnboundedReceiver<Event>;
+
+/// Creates a progress channel; the sender can be cloned freely and handed
+/// to the packaging API or the submit loop.
+pub fn channel() -> (Sender, Receiver) {
+    mpsc::unbounded()
+}
+
+/// Formats a byte count as a human-readable size, e.g. `"1.2 MiB"`.
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}